@@ -17,31 +17,84 @@
 //! let root = doc.root.unwrap();
 //!
 //! let fruit = root.find_child(|tag| tag.name == "fruit").unwrap().clone();
-//! println!("{}", fruit.contents.unwrap());
+//! println!("{}", fruit.text());
 //! ```
 
 extern crate xml;
+#[cfg(feature = "serde")]
+extern crate serde;
 
-use std::collections::HashMap;
-use std::io::Read;
-use std::iter::Filter;
+#[cfg(feature = "serde")]
+mod serde_impl;
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::io::{self, Read, Write};
+use std::iter::{Filter, FilterMap};
 use std::slice::{Iter, IterMut};
 
-use xml::reader::{EventReader, XmlEvent};
+use xml::reader::{EventReader, ParserConfig, XmlEvent};
+
+/// Errors that can occur while parsing a `Document`
+#[derive(Debug)]
+pub enum Error {
+    /// An error from the underlying `xml-rs` reader
+    Xml(xml::reader::Error),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Error::Xml(ref e) => e.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<xml::reader::Error> for Error {
+    fn from(e: xml::reader::Error) -> Error {
+        Error::Xml(e)
+    }
+}
+
+/// A child of an `Element`, kept in document order
+///
+/// Unlike a flat `contents: Option<String>`, an ordered list of `Node`s can represent mixed
+/// content such as `<p>hello <b>world</b> again</p>`, where text is interleaved between child
+/// elements rather than collapsed into a single string.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Node {
+    /// A nested element
+    Element(Element),
+    /// Character data
+    Text(String),
+    /// A `<![CDATA[ ... ]]>` section
+    CData(String),
+    /// An `<!-- ... -->` comment
+    Comment(String),
+    /// A `<?target data?>` processing instruction
+    PI(String, Option<String>),
+}
 
 /// An XML element
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Element {
     /// Tag prefix, used for namespacing: `xsl` in `xsl:for-each`
     pub prefix: Option<String>,
+    /// Namespace URI the tag's prefix (or the in-scope default namespace) resolves to,
+    /// e.g. `http://www.w3.org/1999/XSL/Transform` for `xsl:for-each`
+    pub namespace: Option<String>,
     /// Tag name: `for-each` in `xsl:for-each`
     pub name: String,
-    /// Tag attributes
+    /// Tag attributes, keyed by qualified name (`xsl:if` rather than just `if`)
     pub attributes: HashMap<String, String>,
-    /// A vector of child elements
-    pub children: Vec<Element>,
-    /// Contents of the element
-    pub contents: Option<String>,
+    /// Namespace URIs that attributes' prefixes resolved to while parsing, keyed by the
+    /// same qualified name used in `attributes`; only present for attributes whose prefix
+    /// is bound to a namespace
+    pub attribute_namespaces: HashMap<String, String>,
+    /// Child nodes, in document order
+    pub children: Vec<Node>,
 }
 
 /// An XML document
@@ -51,6 +104,15 @@ pub struct Document {
     pub version: XmlVersion,
     /// Encoding of the XML document
     pub encoding: String,
+    /// Raw contents of the document's `<!DOCTYPE ...>` declaration, if any, excluding the
+    /// surrounding `<!DOCTYPE` and `>` markers
+    pub doctype: Option<String>,
+    /// Named entities declared in the DOCTYPE internal subset, together with the five
+    /// predefined XML entities (`lt`, `gt`, `amp`, `quot`, `apos`). This is informational
+    /// only: `xml-rs` has already substituted all of these into `Characters` text and
+    /// attribute values by the time `parse` returns, so nothing in this crate needs to (or
+    /// does) expand entities itself.
+    pub entities: HashMap<String, String>,
     /// Root tag of the XML document
     pub root: Option<Element>,
 }
@@ -64,14 +126,36 @@ pub enum XmlVersion {
     Version11,
 }
 
+fn node_as_element(node: &Node) -> Option<&Element> {
+    match *node {
+        Node::Element(ref e) => Some(e),
+        _ => None,
+    }
+}
+
+fn node_as_element_mut(node: &mut Node) -> Option<&mut Element> {
+    match *node {
+        Node::Element(ref mut e) => Some(e),
+        _ => None,
+    }
+}
+
+fn node_as_text(node: &Node) -> Option<&str> {
+    match *node {
+        Node::Text(ref s) => Some(s.as_str()),
+        _ => None,
+    }
+}
+
 impl Default for Element {
     fn default() -> Self {
         Element{
             prefix: None,
+            namespace: None,
             name: "tag".to_owned(),
             attributes: HashMap::new(),
+            attribute_namespaces: HashMap::new(),
             children: Vec::new(),
-            contents: None,
         }
     }
 }
@@ -83,34 +167,471 @@ impl Element {
         Element{name: name.into(), .. Element::default()}
     }
 
+    /// The namespace URI this element's prefix (or the in-scope default namespace)
+    /// resolved to while parsing, if any
+    pub fn namespace_uri(&self) -> Option<&str> {
+        self.namespace.as_ref().map(|s| s.as_str())
+    }
+
+    /// Iterates over the `Element` children of this element, skipping text/CData/comment nodes
+    pub fn elements(&self) -> FilterMap<Iter<Node>, fn(&Node) -> Option<&Element>> {
+        self.children.iter().filter_map(node_as_element)
+    }
+
+    /// Iterates over the `Element` children of this element; returns mutable borrows
+    pub fn elements_mut(&mut self) -> FilterMap<IterMut<Node>, fn(&mut Node) -> Option<&mut Element>> {
+        self.children.iter_mut().filter_map(node_as_element_mut)
+    }
+
+    /// Concatenates all the direct `Text` children of this element, in document order
+    pub fn text(&self) -> String {
+        self.children.iter().filter_map(node_as_text).collect()
+    }
+
     /// Find a single child of the current `Element`, given a predicate
     pub fn find_child<P>(&self, predicate: P) -> Option<&Element>
         where P: for<'r> Fn(&'r &Element) -> bool
     {
-        self.children.iter().find(predicate)
+        self.elements().find(predicate)
+    }
+
+    /// Find a single child of the current `Element` by namespace URI and local tag name,
+    /// e.g. `find_child_ns("http://www.w3.org/1999/XSL/Transform", "for-each")`
+    pub fn find_child_ns(&self, uri: &str, local_name: &str) -> Option<&Element> {
+        self.find_child(|e| e.namespace_uri() == Some(uri) && e.name == local_name)
+    }
+
+    /// Look up an attribute by namespace URI and local name, e.g.
+    /// `attribute_ns("http://www.w3.org/1999/xlink", "href")`
+    pub fn attribute_ns(&self, uri: &str, local_name: &str) -> Option<&str> {
+        self.attributes.iter()
+            .find(|&(name, _)| {
+                name.rsplit(':').next() == Some(local_name)
+                    && self.attribute_namespaces.get(name).map(|s| s.as_str()) == Some(uri)
+            })
+            .map(|(_, value)| value.as_str())
     }
 
     /// Find a single child of the current `Element`, given a predicate; returns a mutable borrow
     pub fn find_child_mut<P>(&mut self, predicate: P) -> Option<&mut Element>
         where P: for<'r> FnMut(&'r &mut Element) -> bool
     {
-        self.children.iter_mut().find(predicate)
+        self.elements_mut().find(predicate)
     }
 
     /// Filters the children of the current `Element`, given a predicate
-    pub fn filter_children<P>(&self, predicate: P) -> Filter<Iter<Element>, P>
+    pub fn filter_children<P>(&self, predicate: P) -> Filter<FilterMap<Iter<Node>, fn(&Node) -> Option<&Element>>, P>
         where P: for<'r> Fn(&'r &Element) -> bool
     {
-        self.children.iter().filter(predicate)
+        self.elements().filter(predicate)
     }
 
     /// Filters the children of the current `Element`, given a predicate; returns a mutable iterator
-    pub fn filter_children_mut<P>(&mut self, predicate: P) -> Filter<IterMut<Element>, P>
+    pub fn filter_children_mut<P>(&mut self, predicate: P) -> Filter<FilterMap<IterMut<Node>, fn(&mut Node) -> Option<&mut Element>>, P>
         where P: for<'r> FnMut(&'r &mut Element) -> bool
     {
-        self.children.iter_mut().filter(predicate)
+        self.elements_mut().filter(predicate)
+    }
+
+    /// Selects elements with a compact XPath-like location path: `/`-separated tag names,
+    /// `//` for recursive descendant-or-self search, a 1-based positional index `[n]`, an
+    /// attribute equality test `[@class='foo']`, and an attribute existence test `[@id]`.
+    /// For example, `find_path("//xsl:for-each/xsl:sort[@select][1]")`.
+    pub fn find_path(&self, expr: &str) -> Vec<&Element> {
+        parse_path(expr).iter().fold(vec![self], |context, step| eval_step(context, step))
+    }
+
+    /// Mutable counterpart of `find_path`. For a `//` (descendant-or-self) step, this does
+    /// *not* return nested matches below an outer match: handing back simultaneous `&mut`
+    /// borrows of an element and one of its own descendants isn't possible in safe Rust, so a
+    /// match prunes that branch instead of descending into it. `find_path`, over shared
+    /// references, has no such restriction and returns every matching element including
+    /// descendants of other matches — so `find_path("//b")` and `find_path_mut("//b")` can
+    /// disagree on a tree where a `b` contains another `b`.
+    pub fn find_path_mut(&mut self, expr: &str) -> Vec<&mut Element> {
+        parse_path(expr).iter().fold(vec![self], |context, step| eval_step_mut(context, step))
+    }
+
+    /// Writes this element out, with nested children each on their own (optionally indented)
+    /// line when `indent` is `true`, or fully condensed when `false`. A thin wrapper around
+    /// `write_with_options` kept for backward compatibility; prefer that method for anything
+    /// beyond this one toggle.
+    pub fn write_with<W: Write>(&self, writer: &mut W, indent: bool, indent_string: &str) -> io::Result<()> {
+        let options = WriteOptions{
+            indent: indent,
+            indent_string: indent_string.to_owned(),
+            .. WriteOptions::default()
+        };
+        self.write_with_options(writer, &options)
+    }
+
+    /// Writes this element out according to `options` (indentation, newline style,
+    /// self-closing tags, attribute quoting, and whitespace preservation)
+    pub fn write_with_options<W: Write>(&self, writer: &mut W, options: &WriteOptions) -> io::Result<()> {
+        self.write_indented(writer, options, 0, false, &HashMap::new())
+    }
+
+    fn qualified_name(&self) -> String {
+        match self.prefix {
+            Some(ref prefix) => format!("{}:{}", prefix, self.name),
+            None => self.name.clone(),
+        }
     }
 
+    /// Whether `xml:space="preserve"` is set directly on this element
+    fn preserves_whitespace(&self) -> bool {
+        self.attributes.get("xml:space").map(|v| v == "preserve").unwrap_or(false)
+    }
+
+    /// The prefix portion of a qualified attribute name, e.g. `xlink` in `xlink:href`; entries
+    /// in `attribute_namespaces` are only ever keyed by prefixed names (see its doc comment)
+    fn qualified_name_prefix(qualified: &str) -> Option<String> {
+        qualified.find(':').map(|i| qualified[..i].to_owned())
+    }
+
+    /// Namespace bindings this element needs in scope that aren't already provided by an
+    /// ancestor's `xmlns`/`xmlns:*` declaration: its own `prefix`/`namespace`, plus one per
+    /// distinct prefix in `attribute_namespaces`. Returns the bindings to declare here, and
+    /// `scope` extended with them (for `write_indented` to pass down to its children).
+    fn namespace_decls(&self, scope: &HashMap<Option<String>, String>) -> (Vec<(Option<String>, String)>, HashMap<Option<String>, String>) {
+
+        let mut scope = scope.clone();
+        let mut decls = Vec::new();
+
+        let mut needed: Vec<(Option<String>, &String)> = Vec::new();
+        if let Some(ref uri) = self.namespace {
+            needed.push((self.prefix.clone(), uri));
+        }
+        for (attr_name, uri) in &self.attribute_namespaces {
+            needed.push((Element::qualified_name_prefix(attr_name), uri));
+        }
+
+        for (prefix, uri) in needed {
+            if scope.get(&prefix).map(|s| s.as_str()) != Some(uri.as_str()) {
+                scope.insert(prefix.clone(), uri.clone());
+                decls.push((prefix, uri.clone()));
+            }
+        }
+
+        (decls, scope)
+
+    }
+
+    fn write_indented<W: Write>(&self, writer: &mut W, options: &WriteOptions, level: usize, inherited_preserve: bool, scope: &HashMap<Option<String>, String>) -> io::Result<()> {
+
+        let preserve = inherited_preserve
+            || options.preserve_whitespace.contains(&self.name)
+            || self.preserves_whitespace();
+        let do_indent = options.indent && !preserve;
+        let quote = if options.single_quote_attributes { '\'' } else { '"' };
+
+        let pad = if do_indent { options.indent_string.repeat(level) } else { String::new() };
+        let (decls, scope) = self.namespace_decls(scope);
+
+        write!(writer, "{}<{}", pad, self.qualified_name())?;
+        for (prefix, uri) in &decls {
+            let name = match *prefix {
+                Some(ref p) => format!("xmlns:{}", p),
+                None => "xmlns".to_owned(),
+            };
+            write!(writer, " {}={}{}{}", name, quote, escape_attribute(uri, quote), quote)?;
+        }
+        for (name, value) in &self.attributes {
+            write!(writer, " {}={}{}{}", name, quote, escape_attribute(value, quote), quote)?;
+        }
+
+        if self.children.is_empty() {
+            return if options.self_closing_empty {
+                write!(writer, " />")
+            } else {
+                write!(writer, "></{}>", self.qualified_name())
+            };
+        }
+
+        write!(writer, ">")?;
+        for node in &self.children {
+            match *node {
+                Node::Element(ref e) => {
+                    if do_indent {
+                        write!(writer, "{}", options.newline)?;
+                    }
+                    e.write_indented(writer, options, level + 1, preserve, &scope)?;
+                },
+                Node::Text(ref s) => write!(writer, "{}", escape_text(s))?,
+                Node::CData(ref s) => write!(writer, "<![CDATA[{}]]>", s)?,
+                Node::Comment(ref s) => write!(writer, "<!--{}-->", s)?,
+                Node::PI(ref target, ref data) => match *data {
+                    Some(ref d) => write!(writer, "<?{} {}?>", target, d)?,
+                    None => write!(writer, "<?{}?>", target)?,
+                },
+            }
+        }
+        if do_indent && self.elements().next().is_some() {
+            write!(writer, "{}{}", options.newline, pad)?;
+        }
+        write!(writer, "</{}>", self.qualified_name())
+
+    }
+
+}
+
+impl fmt::Display for Element {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut buf = Vec::new();
+        self.write_with_options(&mut buf, &WriteOptions::default()).map_err(|_| fmt::Error)?;
+        f.write_str(&String::from_utf8_lossy(&buf))
+    }
+}
+
+/// Options controlling how a `Document` or `Element` is serialized back to XML text
+///
+/// Construct with `WriteOptions::default()` and override individual fields, e.g.
+/// `WriteOptions{ xml_decl: false, .. WriteOptions::default() }` for a declaration-less
+/// fragment.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WriteOptions {
+    /// Whether to break child elements onto their own, indented lines at all
+    pub indent: bool,
+    /// The string repeated per nesting level when `indent` is `true`
+    pub indent_string: String,
+    /// The newline sequence written between lines when `indent` is `true`; it also separates
+    /// the declaration and DOCTYPE from what follows them, whatever `indent` is
+    pub newline: String,
+    /// Whether to emit the `<?xml version="..." encoding="..."?>` declaration
+    /// (only consulted by `Document::write_with_options`)
+    pub xml_decl: bool,
+    /// Whether an element with no children is written as a self-closing `<tag />` rather
+    /// than a separate open/close pair `<tag></tag>`
+    pub self_closing_empty: bool,
+    /// Whether attribute values are quoted with `'` instead of the default `"`
+    pub single_quote_attributes: bool,
+    /// Tag names whose subtrees are written without added indentation or newlines, as if
+    /// they carried `xml:space="preserve"`
+    pub preserve_whitespace: HashSet<String>,
+}
+
+impl Default for WriteOptions {
+    fn default() -> Self {
+        WriteOptions{
+            indent: true,
+            indent_string: "  ".to_owned(),
+            newline: "\n".to_owned(),
+            xml_decl: true,
+            self_closing_empty: true,
+            single_quote_attributes: false,
+            preserve_whitespace: HashSet::new(),
+        }
+    }
+}
+
+fn escape_text(s: &str) -> String {
+    s.replace('&', "&amp;").replace('<', "&lt;")
+}
+
+fn escape_attribute(s: &str, quote: char) -> String {
+    let escaped = escape_text(s);
+    if quote == '\'' {
+        escaped.replace('\'', "&apos;")
+    } else {
+        escaped.replace('"', "&quot;")
+    }
+}
+
+/// Whether a path step matches only direct children, or any descendant (or the context node
+/// itself), as written with a `//` separator
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Axis {
+    Child,
+    DescendantOrSelf,
+}
+
+/// A bracketed `[ ... ]` qualifier narrowing the elements a step matches
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Predicate {
+    /// `[n]`: the n-th (1-based) matching element
+    Index(usize),
+    /// `[@name='value']`: has the attribute `name` set to exactly `value`
+    AttrEquals(String, String),
+    /// `[@name]`: has the attribute `name`, regardless of its value
+    AttrExists(String),
+}
+
+/// A single `/`- or `//`-separated location step of a `find_path` expression, e.g. the
+/// `xsl:sort[@select][1]` in `xsl:for-each/xsl:sort[@select][1]`
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Step {
+    axis: Axis,
+    /// The tag prefix to match against `Element::prefix`, e.g. `xsl` in `xsl:sort`
+    prefix: Option<String>,
+    name_test: String,
+    predicates: Vec<Predicate>,
+}
+
+/// Whether `e`'s prefix and local name match a step's name test
+fn step_matches(e: &Element, step: &Step) -> bool {
+    e.name == step.name_test && match step.prefix {
+        Some(ref prefix) => e.prefix.as_ref().map(|p| p == prefix).unwrap_or(false),
+        None => true,
+    }
+}
+
+/// Parses a compact XPath-like expression into its location steps
+fn parse_path(expr: &str) -> Vec<Step> {
+
+    let mut steps = Vec::new();
+    let mut axis = Axis::Child;
+
+    for part in expr.split('/') {
+        if part.is_empty() {
+            axis = Axis::DescendantOrSelf;
+            continue;
+        }
+        let (prefix, name_test, predicates) = parse_step(part);
+        steps.push(Step{axis: axis, prefix: prefix, name_test: name_test, predicates: predicates});
+        axis = Axis::Child;
+    }
+
+    steps
+
+}
+
+/// Parses a single step's text, e.g. `xsl:sort[@select][1]`, into its tag prefix and local
+/// name test, and its predicates
+fn parse_step(step: &str) -> (Option<String>, String, Vec<Predicate>) {
+
+    let name_end = step.find('[').unwrap_or_else(|| step.len());
+    let name_part = &step[..name_end];
+    let (prefix, name_test) = match name_part.find(':') {
+        Some(colon) => (Some(name_part[..colon].to_owned()), name_part[colon + 1..].to_owned()),
+        None => (None, name_part.to_owned()),
+    };
+
+    let mut predicates = Vec::new();
+    let mut rest = &step[name_end..];
+    while let Some(start) = rest.find('[') {
+        rest = &rest[start + 1..];
+        let end = match rest.find(']') {
+            Some(e) => e,
+            None => break,
+        };
+        predicates.push(parse_predicate(&rest[..end]));
+        rest = &rest[end + 1..];
+    }
+
+    (prefix, name_test, predicates)
+
+}
+
+/// Parses the contents of a single `[ ... ]` qualifier
+fn parse_predicate(inner: &str) -> Predicate {
+
+    let inner = inner.trim();
+
+    if let Some(attr) = inner.strip_prefix('@') {
+        match attr.find('=') {
+            Some(eq) => {
+                let name = attr[..eq].trim().to_owned();
+                let mut value = attr[eq + 1..].trim();
+                if value.len() >= 2 && (value.starts_with('\'') || value.starts_with('"')) {
+                    value = &value[1..value.len() - 1];
+                }
+                Predicate::AttrEquals(name, value.to_owned())
+            },
+            None => Predicate::AttrExists(attr.trim().to_owned()),
+        }
+    } else {
+        Predicate::Index(inner.parse().unwrap_or(0))
+    }
+
+}
+
+/// Collects `e` itself together with every descendant element, in document order
+fn descendants_or_self(e: &Element) -> Vec<&Element> {
+    let mut out = vec![e];
+    for child in e.elements() {
+        out.extend(descendants_or_self(child));
+    }
+    out
+}
+
+/// Mutable counterpart of `descendants_or_self`, restricted to what `eval_step_mut` actually
+/// needs: the elements matching `step` within `e`'s subtree (`e` included). Unlike the
+/// immutable version, this does not recurse below a node that already matched `step` — handing
+/// back simultaneous `&mut` borrows of a node and one of its own descendants isn't possible in
+/// safe Rust (mutating the ancestor, e.g. reassigning its children, would dangle the
+/// descendant's reference), so a match prunes that branch instead.
+fn descendants_or_self_mut<'a>(e: &'a mut Element, step: &Step, out: &mut Vec<&'a mut Element>) {
+    if step_matches(e, step) {
+        out.push(e);
+        return;
+    }
+    for child in e.elements_mut() {
+        descendants_or_self_mut(child, step, out);
+    }
+}
+
+fn matches_predicates<'a>(candidates: Vec<&'a Element>, predicates: &[Predicate]) -> Vec<&'a Element> {
+    let mut candidates = candidates;
+    for predicate in predicates {
+        candidates = match *predicate {
+            Predicate::Index(n) if n >= 1 && n <= candidates.len() => vec![candidates[n - 1]],
+            Predicate::Index(_) => Vec::new(),
+            Predicate::AttrEquals(ref name, ref value) => candidates.into_iter()
+                .filter(|e| e.attributes.get(name).map(|v| v == value).unwrap_or(false))
+                .collect(),
+            Predicate::AttrExists(ref name) => candidates.into_iter()
+                .filter(|e| e.attributes.contains_key(name))
+                .collect(),
+        };
+    }
+    candidates
+}
+
+fn matches_predicates_mut<'a>(candidates: Vec<&'a mut Element>, predicates: &[Predicate]) -> Vec<&'a mut Element> {
+    let mut candidates = candidates;
+    for predicate in predicates {
+        candidates = match *predicate {
+            Predicate::Index(n) if n >= 1 && n <= candidates.len() => {
+                vec![candidates.into_iter().nth(n - 1).expect("index already bounds-checked")]
+            },
+            Predicate::Index(_) => Vec::new(),
+            Predicate::AttrEquals(ref name, ref value) => candidates.into_iter()
+                .filter(|e| e.attributes.get(name).map(|v| v == value).unwrap_or(false))
+                .collect(),
+            Predicate::AttrExists(ref name) => candidates.into_iter()
+                .filter(|e| e.attributes.contains_key(name))
+                .collect(),
+        };
+    }
+    candidates
+}
+
+fn eval_step<'a>(context: Vec<&'a Element>, step: &Step) -> Vec<&'a Element> {
+    let mut result = Vec::new();
+    for ctx in context {
+        let candidates: Vec<&Element> = match step.axis {
+            Axis::Child => ctx.elements().filter(|e| step_matches(e, step)).collect(),
+            Axis::DescendantOrSelf => descendants_or_self(ctx).into_iter().filter(|e| step_matches(e, step)).collect(),
+        };
+        result.extend(matches_predicates(candidates, &step.predicates));
+    }
+    result
+}
+
+fn eval_step_mut<'a>(context: Vec<&'a mut Element>, step: &Step) -> Vec<&'a mut Element> {
+    let mut result = Vec::new();
+    for ctx in context {
+        let candidates: Vec<&mut Element> = match step.axis {
+            Axis::Child => ctx.elements_mut().filter(|e| step_matches(e, step)).collect(),
+            Axis::DescendantOrSelf => {
+                let mut found = Vec::new();
+                descendants_or_self_mut(ctx, step, &mut found);
+                found
+            },
+        };
+        result.extend(matches_predicates_mut(candidates, &step.predicates));
+    }
+    result
 }
 
 impl Default for Document {
@@ -118,6 +639,8 @@ impl Default for Document {
         Document{
             version: XmlVersion::Version10,
             encoding: "UTF-8".to_owned(),
+            doctype: None,
+            entities: Document::predefined_entities(),
             root: None,
         }
     }
@@ -135,9 +658,12 @@ impl Document {
     /// # Failures
     ///
     /// Passes any errors that the `xml-rs` library returns up the stack
-    pub fn parse<R: Read>(r: R) -> Result<Document, xml::reader::Error> {
+    pub fn parse<R: Read>(r: R) -> Result<Document, Error> {
 
-        let mut reader = EventReader::new(r);
+        // The default `ParserConfig` has `ignore_comments: true`, which would silently drop
+        // every `<!-- ... -->` before it ever reaches us as an `XmlEvent::Comment`.
+        let config = ParserConfig::new().ignore_comments(false);
+        let mut reader = EventReader::new_with_config(r, config);
         let mut doc = Document::new();
 
         loop {
@@ -155,23 +681,42 @@ impl Document {
                 },
                 XmlEvent::StartElement{name, attributes, ..} => {
 
-                    // Start of the root element
+                    // Start of the root element. `xml-rs` has already resolved prefixes to
+                    // namespace URIs by this point, so there's no need for us to track our
+                    // own scope stack over the (namespace-declaration-free) attribute list.
+                    //
+                    // `XmlEvent` has no `Doctype` variant: the DOCTYPE declaration isn't a
+                    // stream event, it's surfaced via `EventReader::doctype()` once the parser
+                    // has consumed it, which (for a well-formed document) is always before the
+                    // root element's `StartElement` fires. `doctype()` returns the whole
+                    // `<!DOCTYPE ...>` text including its markers, but `doc.doctype` (and the
+                    // writer) only want the inner content, so strip them back off here.
+                    if let Some(dt) = reader.doctype() {
+                        let inner = Document::strip_doctype_markers(dt);
+                        doc.entities = Document::parse_declared_entities(&inner);
+                        doc.doctype = Some(inner);
+                    }
 
                     let mut attr_map = HashMap::new();
+                    let mut attr_ns_map = HashMap::new();
                     for attr in attributes {
                         let attr_name = match attr.name.prefix {
-                            Some(prefix) => format!("{}:{}", prefix, attr.name.local_name),
-                            None => attr.name.local_name,
+                            Some(ref prefix) => format!("{}:{}", prefix, attr.name.local_name),
+                            None => attr.name.local_name.clone(),
                         };
+                        if let Some(ref uri) = attr.name.namespace {
+                            attr_ns_map.insert(attr_name.clone(), uri.clone());
+                        }
                         attr_map.insert(attr_name, attr.value);
                     }
 
                     let root = Element{
-                        prefix: name.prefix,
+                        prefix: name.prefix.clone(),
+                        namespace: name.namespace.clone(),
                         name: name.local_name,
                         attributes: attr_map,
+                        attribute_namespaces: attr_ns_map,
                         children: Vec::new(),
-                        contents: None,
                     };
                     doc.root = Some(try!(Document::parse_children(&mut reader, root)));
 
@@ -185,8 +730,56 @@ impl Document {
 
     }
 
+    /// Strips the surrounding `<!DOCTYPE` and `>` markers from `EventReader::doctype()`'s
+    /// return value, which (unlike `Document::doctype`) includes them
+    fn strip_doctype_markers(raw: &str) -> String {
+        let trimmed = raw.trim();
+        let without_prefix = trimmed.strip_prefix("<!DOCTYPE").unwrap_or(trimmed).trim_start();
+        let without_suffix = without_prefix.strip_suffix('>').unwrap_or(without_prefix);
+        without_suffix.trim().to_owned()
+    }
+
+    /// The five entities every XML document can use without declaring them
+    fn predefined_entities() -> HashMap<String, String> {
+        let mut entities = HashMap::new();
+        entities.insert("lt".to_owned(), "<".to_owned());
+        entities.insert("gt".to_owned(), ">".to_owned());
+        entities.insert("amp".to_owned(), "&".to_owned());
+        entities.insert("quot".to_owned(), "\"".to_owned());
+        entities.insert("apos".to_owned(), "'".to_owned());
+        entities
+    }
+
+    /// Reads the `<!ENTITY name "value">` declarations out of a DOCTYPE internal subset, for
+    /// `Document::entities` only; `xml-rs` does the actual substitution into parsed text
+    /// itself, so this never needs to feed back into parsing
+    fn parse_declared_entities(doctype: &str) -> HashMap<String, String> {
+        let mut entities = Document::predefined_entities();
+        let mut rest = doctype;
+        while let Some(start) = rest.find("<!ENTITY") {
+            rest = rest[start + "<!ENTITY".len()..].trim_start();
+            let name_end = match rest.find(char::is_whitespace) {
+                Some(i) => i,
+                None => break,
+            };
+            let name = rest[..name_end].to_owned();
+            rest = rest[name_end..].trim_start();
+            let quote = match rest.chars().next() {
+                Some(c) if c == '"' || c == '\'' => c,
+                _ => continue, // external or parameter entity declaration; nothing to capture
+            };
+            let value_end = match rest[1..].find(quote) {
+                Some(i) => i,
+                None => break,
+            };
+            entities.insert(name, rest[1..1 + value_end].to_owned());
+            rest = &rest[1 + value_end + 1..];
+        }
+        entities
+    }
+
     /// Internal recursive function to parse children of `element`
-    fn parse_children<R: Read>(mut reader: &mut EventReader<R>, element: Element) -> Result<Element, xml::reader::Error> {
+    fn parse_children<R: Read>(mut reader: &mut EventReader<R>, element: Element) -> Result<Element, Error> {
 
         let mut me = element.clone();
 
@@ -196,22 +789,28 @@ impl Document {
                 XmlEvent::StartElement{name, attributes, ..} => {
 
                     let mut attr_map = HashMap::new();
+                    let mut attr_ns_map = HashMap::new();
                     for attr in attributes {
                         let attr_name = match attr.name.prefix {
-                            Some(prefix) => format!("{}:{}", prefix, attr.name.local_name),
-                            None => attr.name.local_name,
+                            Some(ref prefix) => format!("{}:{}", prefix, attr.name.local_name),
+                            None => attr.name.local_name.clone(),
                         };
+                        if let Some(ref uri) = attr.name.namespace {
+                            attr_ns_map.insert(attr_name.clone(), uri.clone());
+                        }
                         attr_map.insert(attr_name, attr.value);
                     }
 
                     let child = Element{
-                        prefix: name.prefix,
+                        prefix: name.prefix.clone(),
+                        namespace: name.namespace.clone(),
                         name: name.local_name,
                         attributes: attr_map,
+                        attribute_namespaces: attr_ns_map,
                         children: Vec::new(),
-                        contents: None
                     };
-                    me.children.push(try!(Document::parse_children(&mut reader, child)));
+                    let child = try!(Document::parse_children(&mut reader, child));
+                    me.children.push(Node::Element(child));
 
                 },
                 XmlEvent::EndElement{name} => {
@@ -225,28 +824,80 @@ impl Document {
 
                 },
                 XmlEvent::Characters(s) => {
-
-                    let contents = match me.contents {
-                        Some(v) => v,
-                        None => String::new(),
-                    };
-                    me.contents = Some(contents + &s)
-
+                    me.children.push(Node::Text(s));
                 },
                 XmlEvent::CData(s) => {
-
-                    let contents = match me.contents {
-                        Some(v) => v,
-                        None => String::new(),
-                    };
-                    me.contents = Some(contents + "<![CDATA[" + &s + "]]>");
-
+                    me.children.push(Node::CData(s));
                 },
                 XmlEvent::Whitespace(_) => {},
-                XmlEvent::Comment(_) => {},
+                XmlEvent::Comment(s) => {
+                    me.children.push(Node::Comment(s));
+                },
+                XmlEvent::ProcessingInstruction{name, data} => {
+                    me.children.push(Node::PI(name, data));
+                },
                 _ => {},
             }
         }
     }
 
-}
\ No newline at end of file
+}
+
+impl Document {
+
+    /// Writes this document out, with nested children each on their own (optionally indented)
+    /// line when `indent` is `true`, or fully condensed when `false`. A thin wrapper around
+    /// `write_with_options` kept for backward compatibility.
+    pub fn write_with<W: Write>(&self, writer: &mut W, indent: bool, indent_string: &str, xml_decl: bool) -> io::Result<()> {
+        let options = WriteOptions{
+            indent: indent,
+            indent_string: indent_string.to_owned(),
+            xml_decl: xml_decl,
+            .. WriteOptions::default()
+        };
+        self.write_with_options(writer, &options)
+    }
+
+    /// Writes this document out according to `options`, emitting the `<?xml ...?>`
+    /// declaration and `<!DOCTYPE ...>` (if present) before the root element
+    pub fn write_with_options<W: Write>(&self, writer: &mut W, options: &WriteOptions) -> io::Result<()> {
+
+        let mut wrote_header = false;
+
+        if options.xml_decl {
+            let version = match self.version {
+                XmlVersion::Version10 => "1.0",
+                XmlVersion::Version11 => "1.1",
+            };
+            write!(writer, "<?xml version=\"{}\" encoding=\"{}\"?>", version, self.encoding)?;
+            wrote_header = true;
+        }
+
+        if let Some(ref doctype) = self.doctype {
+            if wrote_header {
+                write!(writer, "{}", options.newline)?;
+            }
+            write!(writer, "<!DOCTYPE {}>", doctype)?;
+            wrote_header = true;
+        }
+
+        if let Some(ref root) = self.root {
+            if wrote_header {
+                write!(writer, "{}", options.newline)?;
+            }
+            root.write_with_options(writer, options)?;
+        }
+
+        Ok(())
+
+    }
+
+}
+
+impl fmt::Display for Document {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut buf = Vec::new();
+        self.write_with_options(&mut buf, &WriteOptions::default()).map_err(|_| fmt::Error)?;
+        f.write_str(&String::from_utf8_lossy(&buf))
+    }
+}
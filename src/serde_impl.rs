@@ -0,0 +1,334 @@
+//! `serde` `Serialize`/`Deserialize` support, enabled via the `serde` feature
+//!
+//! Elements (de)serialize into a canonical record shape: `{ "tag", "prefix", "namespace",
+//! "attributes", "attribute_namespaces", "content" }`, where `content` is an ordered array
+//! mixing nested element records, plain strings for text, and dedicated `{ "cdata": .. }` /
+//! `{ "comment": .. }` / `{ "pi": { "target": .., "data": .. } }` records for the other node
+//! kinds. This lets a `Document` round-trip losslessly through `serde_json`, `serde_yaml`, or
+//! any other `serde` format that keeps map keys in the order they were written, and converts
+//! cleanly between XML and JSON. Inside `content`, a record is told apart by its first key, so
+//! a nested element record is only recognised when `tag` comes first (as `Serialize` writes it).
+
+use std::collections::HashMap;
+use std::fmt;
+
+use serde::de::{self, Deserialize, Deserializer, MapAccess, Visitor};
+use serde::ser::{Serialize, SerializeMap, SerializeSeq, Serializer};
+
+use super::{Document, Element, Node, XmlVersion};
+
+impl Serialize for Element {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where S: Serializer
+    {
+        let mut map = serializer.serialize_map(Some(6))?;
+        map.serialize_entry("tag", &self.name)?;
+        map.serialize_entry("prefix", &self.prefix)?;
+        map.serialize_entry("namespace", &self.namespace)?;
+        map.serialize_entry("attributes", &self.attributes)?;
+        map.serialize_entry("attribute_namespaces", &self.attribute_namespaces)?;
+        map.serialize_entry("content", &NodeList(&self.children))?;
+        map.end()
+    }
+}
+
+struct NodeList<'a>(&'a [Node]);
+
+impl<'a> Serialize for NodeList<'a> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where S: Serializer
+    {
+        let mut seq = serializer.serialize_seq(Some(self.0.len()))?;
+        for node in self.0 {
+            seq.serialize_element(&NodeRef(node))?;
+        }
+        seq.end()
+    }
+}
+
+struct NodeRef<'a>(&'a Node);
+
+impl<'a> Serialize for NodeRef<'a> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where S: Serializer
+    {
+        match *self.0 {
+            Node::Element(ref e) => e.serialize(serializer),
+            Node::Text(ref s) => serializer.serialize_str(s),
+            Node::CData(ref s) => {
+                let mut map = serializer.serialize_map(Some(1))?;
+                map.serialize_entry("cdata", s)?;
+                map.end()
+            },
+            Node::Comment(ref s) => {
+                let mut map = serializer.serialize_map(Some(1))?;
+                map.serialize_entry("comment", s)?;
+                map.end()
+            },
+            Node::PI(ref target, ref data) => {
+                let mut map = serializer.serialize_map(Some(1))?;
+                map.serialize_entry("pi", &PiRef{target: target, data: data})?;
+                map.end()
+            },
+        }
+    }
+}
+
+struct PiRef<'a> {
+    target: &'a str,
+    data: &'a Option<String>,
+}
+
+impl<'a> Serialize for PiRef<'a> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where S: Serializer
+    {
+        let mut map = serializer.serialize_map(Some(2))?;
+        map.serialize_entry("target", self.target)?;
+        map.serialize_entry("data", self.data)?;
+        map.end()
+    }
+}
+
+/// Reads the `prefix`/`namespace`/`attributes`/`attribute_namespaces`/`content` fields of an element record, given
+/// its already-known tag name; shared by `Element`'s own visitor and `Node`'s, which peeks the
+/// `tag` key before delegating here
+fn deserialize_element_fields<'de, A>(name: String, mut map: A) -> Result<Element, A::Error>
+    where A: MapAccess<'de>
+{
+    let mut prefix = None;
+    let mut namespace = None;
+    let mut attributes = None;
+    let mut attribute_namespaces = None;
+    let mut content: Option<Vec<Node>> = None;
+
+    while let Some(key) = map.next_key::<String>()? {
+        match key.as_str() {
+            "prefix" => prefix = Some(map.next_value()?),
+            "namespace" => namespace = Some(map.next_value()?),
+            "attributes" => attributes = Some(map.next_value()?),
+            "attribute_namespaces" => attribute_namespaces = Some(map.next_value()?),
+            "content" => content = Some(map.next_value()?),
+            _ => { map.next_value::<de::IgnoredAny>()?; },
+        }
+    }
+
+    Ok(Element{
+        name: name,
+        prefix: prefix.unwrap_or(None),
+        namespace: namespace.unwrap_or(None),
+        attributes: attributes.unwrap_or_else(HashMap::new),
+        attribute_namespaces: attribute_namespaces.unwrap_or_else(HashMap::new),
+        children: content.unwrap_or_else(Vec::new),
+    })
+}
+
+impl<'de> Deserialize<'de> for Element {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where D: Deserializer<'de>
+    {
+        struct ElementVisitor;
+
+        impl<'de> Visitor<'de> for ElementVisitor {
+            type Value = Element;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a { tag, prefix, namespace, attributes, content } element record")
+            }
+
+            fn visit_map<A>(self, mut map: A) -> Result<Element, A::Error>
+                where A: MapAccess<'de>
+            {
+                let mut tag = None;
+                let mut prefix = None;
+                let mut namespace = None;
+                let mut attributes = None;
+                let mut attribute_namespaces = None;
+                let mut content: Option<Vec<Node>> = None;
+
+                while let Some(key) = map.next_key::<String>()? {
+                    match key.as_str() {
+                        "tag" => tag = Some(map.next_value()?),
+                        "prefix" => prefix = Some(map.next_value()?),
+                        "namespace" => namespace = Some(map.next_value()?),
+                        "attributes" => attributes = Some(map.next_value()?),
+                        "attribute_namespaces" => attribute_namespaces = Some(map.next_value()?),
+                        "content" => content = Some(map.next_value()?),
+                        _ => { map.next_value::<de::IgnoredAny>()?; },
+                    }
+                }
+
+                Ok(Element{
+                    name: tag.ok_or_else(|| de::Error::missing_field("tag"))?,
+                    prefix: prefix.unwrap_or(None),
+                    namespace: namespace.unwrap_or(None),
+                    attributes: attributes.unwrap_or_else(HashMap::new),
+                    attribute_namespaces: attribute_namespaces.unwrap_or_else(HashMap::new),
+                    children: content.unwrap_or_else(Vec::new),
+                })
+            }
+        }
+
+        deserializer.deserialize_map(ElementVisitor)
+    }
+}
+
+struct PiRecord {
+    target: String,
+    data: Option<String>,
+}
+
+impl<'de> Deserialize<'de> for PiRecord {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where D: Deserializer<'de>
+    {
+        struct PiVisitor;
+
+        impl<'de> Visitor<'de> for PiVisitor {
+            type Value = PiRecord;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a { target, data } processing instruction record")
+            }
+
+            fn visit_map<A>(self, mut map: A) -> Result<PiRecord, A::Error>
+                where A: MapAccess<'de>
+            {
+                let mut target = None;
+                let mut data = None;
+
+                while let Some(key) = map.next_key::<String>()? {
+                    match key.as_str() {
+                        "target" => target = Some(map.next_value()?),
+                        "data" => data = Some(map.next_value()?),
+                        _ => { map.next_value::<de::IgnoredAny>()?; },
+                    }
+                }
+
+                Ok(PiRecord{
+                    target: target.ok_or_else(|| de::Error::missing_field("target"))?,
+                    data: data.unwrap_or(None),
+                })
+            }
+        }
+
+        deserializer.deserialize_map(PiVisitor)
+    }
+}
+
+impl<'de> Deserialize<'de> for Node {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where D: Deserializer<'de>
+    {
+        struct NodeVisitor;
+
+        impl<'de> Visitor<'de> for NodeVisitor {
+            type Value = Node;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a content item: a string, an element record, or a {cdata}/{comment}/{pi} record")
+            }
+
+            fn visit_str<E>(self, v: &str) -> Result<Node, E>
+                where E: de::Error
+            {
+                Ok(Node::Text(v.to_owned()))
+            }
+
+            fn visit_string<E>(self, v: String) -> Result<Node, E>
+                where E: de::Error
+            {
+                Ok(Node::Text(v))
+            }
+
+            fn visit_map<A>(self, mut map: A) -> Result<Node, A::Error>
+                where A: MapAccess<'de>
+            {
+                match map.next_key::<String>()? {
+                    Some(ref key) if key == "cdata" => Ok(Node::CData(map.next_value()?)),
+                    Some(ref key) if key == "comment" => Ok(Node::Comment(map.next_value()?)),
+                    Some(ref key) if key == "pi" => {
+                        let pi: PiRecord = map.next_value()?;
+                        Ok(Node::PI(pi.target, pi.data))
+                    },
+                    Some(ref key) if key == "tag" => {
+                        let tag = map.next_value()?;
+                        Ok(Node::Element(deserialize_element_fields(tag, map)?))
+                    },
+                    Some(ref other) => Err(de::Error::unknown_field(other, &["tag", "cdata", "comment", "pi"])),
+                    None => Err(de::Error::custom("empty content record")),
+                }
+            }
+        }
+
+        deserializer.deserialize_any(NodeVisitor)
+    }
+}
+
+impl Serialize for Document {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where S: Serializer
+    {
+        let version = match self.version {
+            XmlVersion::Version10 => "1.0",
+            XmlVersion::Version11 => "1.1",
+        };
+        let mut map = serializer.serialize_map(Some(5))?;
+        map.serialize_entry("version", version)?;
+        map.serialize_entry("encoding", &self.encoding)?;
+        map.serialize_entry("doctype", &self.doctype)?;
+        map.serialize_entry("entities", &self.entities)?;
+        map.serialize_entry("root", &self.root)?;
+        map.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for Document {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where D: Deserializer<'de>
+    {
+        struct DocumentVisitor;
+
+        impl<'de> Visitor<'de> for DocumentVisitor {
+            type Value = Document;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a { version, encoding, doctype, entities, root } document record")
+            }
+
+            fn visit_map<A>(self, mut map: A) -> Result<Document, A::Error>
+                where A: MapAccess<'de>
+            {
+                let mut version = None;
+                let mut encoding = None;
+                let mut doctype: Option<Option<String>> = None;
+                let mut entities = None;
+                let mut root = None;
+
+                while let Some(key) = map.next_key::<String>()? {
+                    match key.as_str() {
+                        "version" => {
+                            let v: String = map.next_value()?;
+                            version = Some(if v == "1.1" { XmlVersion::Version11 } else { XmlVersion::Version10 });
+                        },
+                        "encoding" => encoding = Some(map.next_value()?),
+                        "doctype" => doctype = Some(map.next_value()?),
+                        "entities" => entities = Some(map.next_value()?),
+                        "root" => root = Some(map.next_value()?),
+                        _ => { map.next_value::<de::IgnoredAny>()?; },
+                    }
+                }
+
+                Ok(Document{
+                    version: version.unwrap_or(XmlVersion::Version10),
+                    encoding: encoding.unwrap_or_else(|| "UTF-8".to_owned()),
+                    doctype: doctype.unwrap_or(None),
+                    entities: entities.unwrap_or_else(Document::predefined_entities),
+                    root: root.unwrap_or(None),
+                })
+            }
+        }
+
+        deserializer.deserialize_map(DocumentVisitor)
+    }
+}
@@ -0,0 +1,116 @@
+extern crate treexml;
+
+mod find_path {
+
+    mod immutable {
+
+        use treexml::Document;
+
+        #[test]
+        fn child_axis_matches_direct_children_only() {
+
+            let xml = "<a><b id=\"1\"><b id=\"2\" /></b></a>";
+            let doc = Document::parse(xml.as_bytes()).unwrap();
+            let root = doc.root.unwrap();
+
+            let found = root.find_path("b");
+            assert_eq!(found.len(), 1);
+            assert_eq!(found[0].attributes.get("id").map(|s| s.as_str()), Some("1"));
+
+        }
+
+        #[test]
+        fn descendant_axis_matches_nested_elements() {
+
+            let xml = "<a><b id=\"1\"><b id=\"2\" /></b></a>";
+            let doc = Document::parse(xml.as_bytes()).unwrap();
+            let root = doc.root.unwrap();
+
+            let found = root.find_path("//b");
+            assert_eq!(found.len(), 2);
+
+        }
+
+        #[test]
+        fn index_predicate_selects_the_nth_match() {
+
+            let xml = "<a><b id=\"1\" /><b id=\"2\" /><b id=\"3\" /></a>";
+            let doc = Document::parse(xml.as_bytes()).unwrap();
+            let root = doc.root.unwrap();
+
+            let found = root.find_path("b[2]");
+            assert_eq!(found.len(), 1);
+            assert_eq!(found[0].attributes.get("id").map(|s| s.as_str()), Some("2"));
+
+        }
+
+        #[test]
+        fn attr_predicates_filter_by_presence_and_value() {
+
+            let xml = "<a><b select=\"x\" /><b /><b select=\"y\" /></a>";
+            let doc = Document::parse(xml.as_bytes()).unwrap();
+            let root = doc.root.unwrap();
+
+            assert_eq!(root.find_path("b[@select]").len(), 2);
+            assert_eq!(root.find_path("b[@select='y']").len(), 1);
+
+        }
+
+        #[test]
+        fn prefix_is_matched_against_the_element_prefix() {
+
+            let xml = concat!(
+                r#"<xsl:stylesheet xmlns:xsl="http://xsl.example">"#,
+                r#"<xsl:for-each><xsl:sort select="a" /><sort /></xsl:for-each>"#,
+                r#"</xsl:stylesheet>"#,
+            );
+            let doc = Document::parse(xml.as_bytes()).unwrap();
+            let root = doc.root.unwrap();
+
+            let found = root.find_path("//xsl:for-each/xsl:sort[@select][1]");
+            assert_eq!(found.len(), 1);
+            assert_eq!(found[0].attributes.get("select").map(|s| s.as_str()), Some("a"));
+
+        }
+
+    }
+
+    mod mutable {
+
+        use treexml::Document;
+
+        #[test]
+        fn find_path_mut_allows_editing_matched_elements() {
+
+            let xml = "<a><b id=\"1\" /><b id=\"2\" /></a>";
+            let mut doc = Document::parse(xml.as_bytes()).unwrap();
+            let root = doc.root.as_mut().unwrap();
+
+            for b in root.find_path_mut("b") {
+                b.attributes.insert("touched".to_owned(), "yes".to_owned());
+            }
+
+            for b in root.elements() {
+                assert_eq!(b.attributes.get("touched").map(|s| s.as_str()), Some("yes"));
+            }
+
+        }
+
+        #[test]
+        fn descendant_axis_does_not_descend_below_a_match() {
+
+            let xml = "<a><b><b /></b></a>";
+            let mut doc = Document::parse(xml.as_bytes()).unwrap();
+            let root = doc.root.as_mut().unwrap();
+
+            // The inner `b` is a descendant of the outer match, so only the outer one is
+            // returned: handing back `&mut` borrows of both an element and its own descendant
+            // isn't possible in safe Rust.
+            let found = root.find_path_mut("//b");
+            assert_eq!(found.len(), 1);
+
+        }
+
+    }
+
+}
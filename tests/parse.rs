@@ -0,0 +1,149 @@
+extern crate treexml;
+
+mod parse {
+
+    mod namespaces {
+
+        use treexml::Document;
+
+        #[test]
+        fn default_namespace_resolves_on_the_declaring_element() {
+
+            let xml = r#"<root xmlns="http://default.example"><child /></root>"#;
+            let doc = Document::parse(xml.as_bytes()).unwrap();
+            let root = doc.root.unwrap();
+
+            assert_eq!(root.namespace_uri(), Some("http://default.example"));
+            assert_eq!(root.elements().next().unwrap().namespace_uri(), Some("http://default.example"));
+
+        }
+
+        #[test]
+        fn prefixed_namespace_resolves_on_descendants() {
+
+            let xml = concat!(
+                r#"<root xmlns:xsl="http://xsl.example">"#,
+                r#"<xsl:for-each id="1" /></root>"#,
+            );
+            let doc = Document::parse(xml.as_bytes()).unwrap();
+            let root = doc.root.unwrap();
+            let for_each = root.find_child_ns("http://xsl.example", "for-each").unwrap();
+
+            assert_eq!(for_each.prefix, Some("xsl".to_owned()));
+            assert_eq!(for_each.namespace_uri(), Some("http://xsl.example"));
+
+        }
+
+        #[test]
+        fn attribute_namespace_is_resolved_separately_from_the_element() {
+
+            let xml = concat!(
+                r#"<root xmlns:xlink="http://www.w3.org/1999/xlink">"#,
+                r#"<a xlink:href="http://example.com" /></root>"#,
+            );
+            let doc = Document::parse(xml.as_bytes()).unwrap();
+            let root = doc.root.unwrap();
+            let a = root.elements().next().unwrap();
+
+            assert_eq!(a.namespace_uri(), None);
+            assert_eq!(a.attribute_ns("http://www.w3.org/1999/xlink", "href"), Some("http://example.com"));
+
+        }
+
+    }
+
+    mod doctype {
+
+        use treexml::Document;
+
+        #[test]
+        fn internal_subset_is_captured_verbatim() {
+
+            let xml = concat!(
+                "<!DOCTYPE root [<!ENTITY copy \"(c)\">]>",
+                "<root>&copy; 2024</root>",
+            );
+            let doc = Document::parse(xml.as_bytes()).unwrap();
+
+            assert_eq!(doc.doctype, Some("root [<!ENTITY copy \"(c)\">]".to_owned()));
+
+        }
+
+        #[test]
+        fn internal_entities_are_expanded_by_the_reader() {
+
+            // `xml-rs` expands general internal entities itself while parsing, before handing
+            // us a `Characters` event, so no entity handling is needed on our side.
+            let xml = concat!(
+                "<!DOCTYPE root [<!ENTITY copy \"(c)\">]>",
+                "<root>&copy; 2024</root>",
+            );
+            let doc = Document::parse(xml.as_bytes()).unwrap();
+            let root = doc.root.unwrap();
+
+            assert_eq!(root.text(), "(c) 2024");
+
+        }
+
+        #[test]
+        fn documents_without_a_doctype_leave_it_unset() {
+
+            let doc = Document::parse("<root />".as_bytes()).unwrap();
+
+            assert_eq!(doc.doctype, None);
+
+        }
+
+        #[test]
+        fn declared_entities_are_captured_alongside_the_predefined_ones() {
+
+            let xml = concat!(
+                "<!DOCTYPE root [<!ENTITY copy \"(c)\">]>",
+                "<root>&copy; 2024</root>",
+            );
+            let doc = Document::parse(xml.as_bytes()).unwrap();
+
+            assert_eq!(doc.entities.get("copy"), Some(&"(c)".to_owned()));
+            assert_eq!(doc.entities.get("lt"), Some(&"<".to_owned()));
+            assert_eq!(doc.entities.get("amp"), Some(&"&".to_owned()));
+
+        }
+
+        #[test]
+        fn predefined_entities_are_present_without_a_doctype() {
+
+            let doc = Document::parse("<root />".as_bytes()).unwrap();
+
+            assert_eq!(doc.entities.get("quot"), Some(&"\"".to_owned()));
+            assert_eq!(doc.entities.get("apos"), Some(&"'".to_owned()));
+            assert_eq!(doc.entities.len(), 5);
+
+        }
+
+    }
+
+    mod mixed_content {
+
+        use treexml::{Document, Node};
+
+        #[test]
+        fn comments_and_text_are_kept_in_document_order() {
+
+            let xml = "<p>hello <!--note--><b>world</b> again</p>";
+            let doc = Document::parse(xml.as_bytes()).unwrap();
+            let root = doc.root.unwrap();
+
+            assert_eq!(root.children.len(), 4);
+            assert_eq!(root.children[0], Node::Text("hello ".to_owned()));
+            assert_eq!(root.children[1], Node::Comment("note".to_owned()));
+            match root.children[2] {
+                Node::Element(ref e) => assert_eq!(e.name, "b"),
+                ref other => panic!("expected an element, got {:?}", other),
+            }
+            assert_eq!(root.children[3], Node::Text(" again".to_owned()));
+
+        }
+
+    }
+
+}
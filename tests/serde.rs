@@ -0,0 +1,65 @@
+#![cfg(feature = "serde")]
+
+extern crate treexml;
+extern crate serde_json;
+
+mod serde {
+
+    use treexml::{Document, Element, Node};
+
+    #[test]
+    fn element_round_trips_through_json() {
+
+        let mut root = Element::new("root");
+        root.prefix = Some("xsl".to_owned());
+        root.namespace = Some("http://xsl.example".to_owned());
+        root.attributes.insert("id".to_owned(), "1".to_owned());
+        root.children.push(Node::Text("hello".to_owned()));
+        root.children.push(Node::Element(Element::new("child")));
+
+        let json = serde_json::to_string(&root).unwrap();
+        let round_tripped: Element = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(round_tripped, root);
+
+    }
+
+    #[test]
+    fn document_round_trips_through_json() {
+
+        let mut root = Element::new("root");
+        root.children.push(Node::CData("data".to_owned()));
+
+        let doc = Document {
+            doctype: Some("root SYSTEM \"root.dtd\"".to_owned()),
+            root: Some(root),
+            ..Document::default()
+        };
+
+        let json = serde_json::to_string(&doc).unwrap();
+        let round_tripped: Document = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(round_tripped, doc);
+
+    }
+
+    #[test]
+    fn content_items_serialize_to_their_documented_shapes() {
+
+        let mut root = Element::new("root");
+        root.children.push(Node::Text("text".to_owned()));
+        root.children.push(Node::CData("cdata".to_owned()));
+        root.children.push(Node::Comment("comment".to_owned()));
+        root.children.push(Node::PI("target".to_owned(), Some("data".to_owned())));
+
+        let value = serde_json::to_value(&root).unwrap();
+        let content = value.get("content").unwrap().as_array().unwrap();
+
+        assert_eq!(content[0], serde_json::json!("text"));
+        assert_eq!(content[1], serde_json::json!({"cdata": "cdata"}));
+        assert_eq!(content[2], serde_json::json!({"comment": "comment"}));
+        assert_eq!(content[3], serde_json::json!({"pi": {"target": "target", "data": "data"}}));
+
+    }
+
+}
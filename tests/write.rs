@@ -4,14 +4,14 @@ mod write {
 
     mod document {
 
-        use treexml::{Document, Element};
+        use treexml::{Document, Element, Node};
 
         #[test]
         fn simple_document() {
 
             let mut root = Element::new("root");
             let child = Element::new("child");
-            root.children.push(child);
+            root.children.push(Node::Element(child));
 
             let doc = Document {
                 root: Some(root),
@@ -34,7 +34,7 @@ mod write {
 
             let mut root = Element::new("root");
             let child = Element::new("child");
-            root.children.push(child);
+            root.children.push(Node::Element(child));
 
             let doc = Document {
                 root: Some(root),
@@ -54,7 +54,7 @@ mod write {
 
     mod element {
 
-        use treexml::{Document, Element};
+        use treexml::{Document, Element, Node};
 
         #[test]
         fn stringify() {
@@ -62,7 +62,7 @@ mod write {
             let mut root = Element::new("root");
             let child = Element::new("child");
             let child2 = Element::new("child").clone();
-            root.children.push(child);
+            root.children.push(Node::Element(child));
 
             let _ = Document {
                 root: Some(root),
@@ -79,13 +79,13 @@ mod write {
 
     mod contents {
 
-        use treexml::{Document, Element};
+        use treexml::{Document, Element, Node};
 
         #[test]
         fn plain_text() {
 
             let mut root = Element::new("root");
-            root.text = Some("text".to_owned());
+            root.children.push(Node::Text("text".to_owned()));
 
             let doc = Document {
                 root: Some(root),
@@ -105,7 +105,7 @@ mod write {
         fn tags_in_text() {
 
             let mut root = Element::new("root");
-            root.text = Some("<tag />".to_owned());
+            root.children.push(Node::Text("<tag />".to_owned()));
 
             let doc = Document {
                 root: Some(root),
@@ -121,17 +121,29 @@ mod write {
 
         }
 
+        #[test]
+        fn mixed_content() {
+
+            let mut root = Element::new("p");
+            root.children.push(Node::Text("hello ".to_owned()));
+            root.children.push(Node::Element(Element::new("b")));
+            root.children.push(Node::Text(" again".to_owned()));
+
+            assert_eq!(root.text(), "hello  again");
+
+        }
+
     }
 
     mod cdata {
 
-        use treexml::{Document, Element};
+        use treexml::{Document, Element, Node};
 
         #[test]
         fn plain_text() {
 
             let mut root = Element::new("root");
-            root.cdata = Some("data".to_owned());
+            root.children.push(Node::CData("data".to_owned()));
 
             let doc = Document {
                 root: Some(root),
@@ -151,7 +163,7 @@ mod write {
         fn nested_tags() {
 
             let mut root = Element::new("root");
-            root.cdata = Some("<tag />".to_owned());
+            root.children.push(Node::CData("<tag />".to_owned()));
 
             let doc = Document {
                 root: Some(root),
@@ -169,29 +181,15 @@ mod write {
 
     }
 
-    mod builder {
-        use treexml::{Document, ElementBuilder};
-
-        #[test]
-        fn incremental_build() {
-
-            let root = ElementBuilder::new("root")
-                .children(vec![
-                    ElementBuilder::new("list")
-                        .children(vec![
-                            ElementBuilder::new("child").element(),
-                            ElementBuilder::new("child")
-                                .attr("class", "foo")
-                                .text("bar")
-                                .element(),
-                            ElementBuilder::new("child")
-                                .attr("class", 22.to_string())
-                                .text(11.to_string())
-                                .element(),
-                        ])
-                        .element(),
-                ])
-                .element();
+    mod comment_and_pi {
+
+        use treexml::{Document, Element, Node};
+
+        #[test]
+        fn comment() {
+
+            let mut root = Element::new("root");
+            root.children.push(Node::Comment(" note ".to_owned()));
 
             let doc = Document {
                 root: Some(root),
@@ -200,36 +198,171 @@ mod write {
 
             let doc_ref = concat!(
                 "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n",
-                "<root>\n",
-                "  <list>\n",
-                "    <child />\n",
-                "    <child class=\"foo\">bar</child>\n",
-                "    <child class=\"22\">11</child>\n",
-                "  </list>\n",
-                "</root>"
+                "<root><!-- note --></root>",
+            );
+
+            assert_eq!(doc.to_string(), doc_ref);
+
+        }
+
+        #[test]
+        fn processing_instruction() {
+
+            let mut root = Element::new("root");
+            root.children.push(Node::PI("xml-stylesheet".to_owned(), Some("type=\"text/xsl\"".to_owned())));
+
+            let doc = Document {
+                root: Some(root),
+                ..Document::default()
+            };
+
+            let doc_ref = concat!(
+                "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n",
+                "<root><?xml-stylesheet type=\"text/xsl\"?></root>",
             );
 
             assert_eq!(doc.to_string(), doc_ref);
 
         }
 
+    }
+
+    mod namespaces {
+
+        use treexml::Document;
+
+        #[test]
+        fn round_tripping_a_parsed_document_redeclares_the_namespaces_it_uses() {
+
+            let xml = concat!(
+                r#"<root xmlns="http://d.example" xmlns:xsl="http://xsl.example" id="1">"#,
+                r#"<xsl:for-each /></root>"#,
+            );
+            let doc = Document::parse(xml.as_bytes()).unwrap();
+            let root = doc.root.unwrap();
+
+            let doc_ref = concat!(
+                "<root xmlns=\"http://d.example\" id=\"1\">\n",
+                "  <xsl:for-each xmlns:xsl=\"http://xsl.example\" />\n",
+                "</root>",
+            );
+
+            assert_eq!(root.to_string(), doc_ref);
+
+        }
+
+        #[test]
+        fn a_namespace_already_in_scope_is_not_redeclared_on_a_child() {
+
+            let xml = concat!(
+                r#"<xsl:root xmlns:xsl="http://xsl.example">"#,
+                r#"<xsl:child /></xsl:root>"#,
+            );
+            let doc = Document::parse(xml.as_bytes()).unwrap();
+            let root = doc.root.unwrap();
+
+            let doc_ref = concat!(
+                "<xsl:root xmlns:xsl=\"http://xsl.example\">\n",
+                "  <xsl:child />\n",
+                "</xsl:root>",
+            );
+
+            assert_eq!(root.to_string(), doc_ref);
+
+        }
+
+    }
+
+    mod options {
+
+        use treexml::{Document, Element, Node, WriteOptions};
+
+        #[test]
+        fn no_self_closing_tag() {
+
+            let root = Element::new("root");
+            let options = WriteOptions {
+                self_closing_empty: false,
+                ..WriteOptions::default()
+            };
+
+            let mut out = vec![];
+            root.write_with_options(&mut out, &options).unwrap();
+
+            assert_eq!(String::from_utf8(out).unwrap(), "<root></root>");
+
+        }
+
+        #[test]
+        fn no_xml_decl() {
+
+            let root = Element::new("root");
+            let doc = Document {
+                root: Some(root),
+                ..Document::default()
+            };
+            let options = WriteOptions {
+                xml_decl: false,
+                ..WriteOptions::default()
+            };
+
+            let mut out = vec![];
+            doc.write_with_options(&mut out, &options).unwrap();
+
+            assert_eq!(String::from_utf8(out).unwrap(), "<root />");
+
+        }
+
+        #[test]
+        fn single_quote_attributes() {
+
+            let mut root = Element::new("root");
+            root.attributes.insert("key".to_owned(), "value".to_owned());
+            let options = WriteOptions {
+                single_quote_attributes: true,
+                ..WriteOptions::default()
+            };
+
+            let mut out = vec![];
+            root.write_with_options(&mut out, &options).unwrap();
+
+            assert_eq!(String::from_utf8(out).unwrap(), "<root key='value' />");
+
+        }
+
+        #[test]
+        fn preserve_whitespace() {
+
+            let mut root = Element::new("pre");
+            root.children.push(Node::Text("  two  spaces  ".to_owned()));
+            let mut options = WriteOptions::default();
+            options.preserve_whitespace.insert("pre".to_owned());
+
+            let mut out = vec![];
+            root.write_with_options(&mut out, &options).unwrap();
+
+            assert_eq!(String::from_utf8(out).unwrap(), "<pre>  two  spaces  </pre>");
+
+        }
+
         #[test]
-        fn incremental_build_multiline() {
-            let mut root = ElementBuilder::new("root");
-            root.attr("key", "value");
-            root.text("some-text");
+        fn doctype() {
 
+            let root = Element::new("root");
             let doc = Document {
-                root: Some(root.element()),
+                doctype: Some("root SYSTEM \"root.dtd\"".to_owned()),
+                root: Some(root),
                 ..Document::default()
             };
 
             let doc_ref = concat!(
                 "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n",
-                "<root key=\"value\">some-text</root>"
+                "<!DOCTYPE root SYSTEM \"root.dtd\">\n",
+                "<root />",
             );
 
             assert_eq!(doc.to_string(), doc_ref);
+
         }
 
     }